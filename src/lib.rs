@@ -2,11 +2,16 @@
 //!
 //! This library at its core provides two macros, [`hit!`] and [`check!`],
 //! which can be used to verify that a certain test exercises a certain code
-//! path.
+//! path. Marks are declared up front with [`def!`], so that referencing a
+//! mark that doesn't exist is a compile error rather than a typo that
+//! silently never fires.
 //!
 //! Here's a short example:
 //!
 //! ```
+//! cov_mark::def!(short_date);
+//! cov_mark::def!(bad_dashes);
+//!
 //! fn parse_date(s: &str) -> Option<(u32, u32, u32)> {
 //!     if 10 != s.len() {
 //!         // By using `cov_mark::hit!`
@@ -58,26 +63,89 @@
 //! * Making sure that code and tests don't diverge during refactorings.
 //! * (If used pervasively) Verifying that each branch has a corresponding test.
 //!
+//! # Cargo Features
+//!
+//! Instrumentation is active -- [`hit!`] actually records hits and [`check!`]
+//! actually checks them -- whenever the `enable` feature is turned on, the
+//! `report` feature is turned on, *or* `debug_assertions` are enabled (as
+//! they are by default in `cargo test` and debug builds). This way `hit!`
+//! sites stay live in, say, a fuzzing harness that isn't itself
+//! `#[cfg(test)]`, while release builds (where none of the three hold)
+//! compile [`hit!`] and all of the check macros down to a no-op so coverage
+//! marks cost nothing in production.
+//!
+//! The `report` feature additionally turns on `report()`, which finds every
+//! [`def!`]-declared mark that the suite neither hit nor watched with a check
+//! macro, or hit but never checked.
+//! Turning on `report` always activates instrumentation (even in release
+//! builds without `enable`), since a mark that's compiled down to a no-op
+//! can't be reported on accurately.
+//!
 //! # Limitations
 //!
-//! * Names of marks must be globally unique.
+//! * A mark declared with [`def!`] must be in scope at every [`hit!`], [`check!`]
+//!   and [`check_count!`] site that names it, same as any other item.
+//! * By default, [`hit!`] only sees [`check!`]/[`check_count!`] guards set up
+//!   on the *same* thread. If the code under test spawns worker threads, use
+//!   [`check_threaded!`]/[`check_count_threaded!`] instead, which track hits
+//!   across all threads. A threaded guard also counts hits made by other
+//!   tests running at the same time, so two concurrently running tests that
+//!   both use the same mark, when at least one of them watches it with a
+//!   threaded guard, is not supported.
+//! * [`check_threaded!`]/[`check_count_threaded!`] only see hits from worker
+//!   threads that have actually run by the time the guard's scope ends --
+//!   `.join()` any threads you expect to hit the mark before the guard drops,
+//!   or the hit can race the guard's teardown and be missed.
 //!
 //! # Implementation Details
 //!
-//! Each coverage mark is an `AtomicUsize` counter. [`hit!`] increments
+//! [`def!`] expands to a `static` item, so each coverage mark has a unique
+//! `'static` address. [`hit!`] and [`check!`]/[`check_count!`] identify a mark
+//! by that address rather than by its name, which is both faster than string
+//! comparison and is checked by the compiler (an unresolved mark is a compile
+//! error, and editors can jump straight from a `hit!` to its `def!`).
+//! Each mark is a counter under the hood. [`hit!`] increments
 //! this counter, [`check!`] returns a guard object which checks that
 //! the mark was incremented.
-//! Each counter is stored as a thread-local, allowing for accurate per-thread
-//! counting.
+//! By default each counter is stored as a thread-local, allowing for accurate
+//! per-thread counting at no cost to threads that aren't under test;
+//! [`check_threaded!`] and [`check_count_threaded!`] instead register the
+//! counter in a process-global registry that [`hit!`] also consults.
 
 #![deny(rustdoc::broken_intra_doc_links)]
 #![allow(clippy::test_attr_in_doctest)]
 
+/// Declares a mark, so that it can be used with [`hit!`], [`check!`] and
+/// [`check_count!`].
+///
+/// This expands to an item (a `static`), so `def!` should be called at module
+/// (or block) scope, and the declared mark must be in scope wherever it is
+/// referenced -- referencing an undeclared mark is a compile error.
+///
+/// # Example
+///
+/// ```
+/// cov_mark::def!(save_divide_zero);
+/// ```
+#[macro_export]
+macro_rules! def {
+    ($ident:ident) => {
+        #[allow(non_upper_case_globals)]
+        static $ident: $crate::__rt::Mark =
+            $crate::__rt::Mark::new(concat!(module_path!(), "::", stringify!($ident)));
+
+        #[cfg(feature = "report")]
+        $crate::__rt::inventory::submit! { $crate::report::MarkHandle(&$ident) }
+    };
+}
+
 /// Hit a mark with a specified name.
 ///
 /// # Example
 ///
 /// ```
+/// cov_mark::def!(save_divide_zero);
+///
 /// fn safe_divide(dividend: u32, divisor: u32) -> u32 {
 ///     if divisor == 0 {
 ///         cov_mark::hit!(save_divide_zero);
@@ -89,7 +157,7 @@
 #[macro_export]
 macro_rules! hit {
     ($ident:ident) => {
-        $crate::__rt::hit(stringify!($ident))
+        $crate::__rt::hit(&$ident)
     };
 }
 
@@ -98,6 +166,8 @@ macro_rules! hit {
 /// # Example
 ///
 /// ```
+/// cov_mark::def!(save_divide_zero);
+///
 /// #[test]
 /// fn test_safe_divide_by_zero() {
 ///     cov_mark::check!(save_divide_zero);
@@ -114,15 +184,19 @@ macro_rules! hit {
 #[macro_export]
 macro_rules! check {
     ($ident:ident) => {
-        let _guard = $crate::__rt::Guard::new(stringify!($ident), None);
+        let _guard = $crate::__rt::Guard::new(&$ident, $crate::__rt::Expectation::AtLeastOnce);
     };
 }
 
-/// Checks that a specified mark was hit exactly the specified number of times.
+/// Checks that a specified mark was hit a number of times matching the given
+/// count or range, e.g. `check_count!(mark, 2)`, `check_count!(mark, 2..=5)`
+/// or `check_count!(mark, 1..)`.
 ///
 /// # Example
 ///
 /// ```
+/// cov_mark::def!(covered_dropper_drops);
+///
 /// struct CoveredDropper;
 /// impl Drop for CoveredDropper {
 ///     fn drop(&mut self) {
@@ -136,21 +210,244 @@ macro_rules! check {
 ///     let _covered_dropper1 = CoveredDropper;
 ///     let _covered_dropper2 = CoveredDropper;
 /// }
+///
+/// #[test]
+/// fn drop_count_range_test() {
+///     cov_mark::check_count!(covered_dropper_drops, 1..=2);
+///     let _covered_dropper1 = CoveredDropper;
+/// }
 /// ```
 #[macro_export]
 macro_rules! check_count {
     ($ident:ident, $count: literal) => {
-        let _guard = $crate::__rt::Guard::new(stringify!($ident), Some($count));
+        let _guard =
+            $crate::__rt::Guard::new(&$ident, $crate::__rt::Expectation::exact($count));
+    };
+    ($ident:ident, $range: expr) => {
+        let _guard =
+            $crate::__rt::Guard::new(&$ident, $crate::__rt::Expectation::range($range));
+    };
+}
+
+/// Checks that a specified mark is *not* hit by the end of the scope, i.e.
+/// that its hit count stays at zero.
+///
+/// # Example
+///
+/// ```
+/// cov_mark::def!(save_divide_zero);
+///
+/// fn safe_divide(dividend: u32, divisor: u32) -> u32 {
+///     if divisor == 0 {
+///         cov_mark::hit!(save_divide_zero);
+///         return 0;
+///     }
+///     dividend / divisor
+/// }
+///
+/// #[test]
+/// fn test_safe_divide_never_zero() {
+///     cov_mark::check_absent!(save_divide_zero);
+///     assert_eq!(safe_divide(92, 4), 23);
+/// }
+/// ```
+#[macro_export]
+macro_rules! check_absent {
+    ($ident:ident) => {
+        let _guard = $crate::__rt::Guard::new(&$ident, $crate::__rt::Expectation::exact(0));
+    };
+}
+
+/// Like [`check!`], but also sees hits from [`hit!`] calls made on other
+/// threads.
+///
+/// # Example
+///
+/// ```
+/// cov_mark::def!(worker_ran);
+///
+/// #[test]
+/// fn test_worker_runs() {
+///     cov_mark::check_threaded!(worker_ran);
+///     std::thread::spawn(|| cov_mark::hit!(worker_ran))
+///         .join()
+///         .unwrap();
+/// }
+/// ```
+#[macro_export]
+macro_rules! check_threaded {
+    ($ident:ident) => {
+        let _guard =
+            $crate::__rt::Guard::new_threaded(&$ident, $crate::__rt::Expectation::AtLeastOnce);
+    };
+}
+
+/// Like [`check_count!`], but also sees hits from [`hit!`] calls made on
+/// other threads.
+///
+/// # Example
+///
+/// ```
+/// cov_mark::def!(worker_ran);
+///
+/// #[test]
+/// fn test_worker_runs_twice() {
+///     cov_mark::check_count_threaded!(worker_ran, 2);
+///     let workers: Vec<_> = (0..2)
+///         .map(|_| std::thread::spawn(|| cov_mark::hit!(worker_ran)))
+///         .collect();
+///     for worker in workers {
+///         worker.join().unwrap();
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! check_count_threaded {
+    ($ident:ident, $count: literal) => {
+        let _guard = $crate::__rt::Guard::new_threaded(
+            &$ident,
+            $crate::__rt::Expectation::exact($count),
+        );
+    };
+    ($ident:ident, $range: expr) => {
+        let _guard = $crate::__rt::Guard::new_threaded(
+            &$ident,
+            $crate::__rt::Expectation::range($range),
+        );
     };
 }
 
+/// A declared coverage mark.
+///
+/// Don't construct this directly, use [`def!`] instead -- the identity of a
+/// mark is the `'static` address of its `Mark`, so two `def!`s always produce
+/// distinct marks even if they happen to share a name.
 #[doc(hidden)]
-#[cfg(feature = "enable")]
+pub struct Mark {
+    #[cfg_attr(
+        not(any(feature = "enable", feature = "report", debug_assertions)),
+        allow(dead_code)
+    )]
+    name: &'static str,
+    #[cfg(feature = "report")]
+    hit: std::sync::atomic::AtomicBool,
+    #[cfg(feature = "report")]
+    checked: std::sync::atomic::AtomicBool,
+}
+
+impl Mark {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str) -> Mark {
+        Mark {
+            name,
+            #[cfg(feature = "report")]
+            hit: std::sync::atomic::AtomicBool::new(false),
+            #[cfg(feature = "report")]
+            checked: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    #[cfg(feature = "report")]
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[cfg(feature = "report")]
+    fn record_hit(&self) {
+        self.hit.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "report")]
+    fn record_checked(&self) {
+        self.checked
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "report")]
+    fn was_hit(&self) -> bool {
+        self.hit.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    #[cfg(feature = "report")]
+    fn was_checked(&self) -> bool {
+        self.checked.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// Whole-suite coverage reporting: tracks which [`def!`]-declared marks were
+/// never [`hit!`] nor watched by any check macro, and which were hit but never
+/// watched by a check macro anywhere in the suite.
+#[cfg(feature = "report")]
+pub mod report {
+    use crate::Mark;
+
+    #[doc(hidden)]
+    pub struct MarkHandle(pub &'static Mark);
+
+    inventory::collect!(MarkHandle);
+
+    /// The result of [`crate::report()`].
+    #[derive(Debug, Default)]
+    pub struct Report {
+        /// Marks that were declared with [`crate::def!`] but never hit by a
+        /// [`crate::hit!`] call anywhere in the suite. A never-hit mark that
+        /// some check macro watched (e.g. [`crate::check_absent!`]) is not
+        /// listed.
+        pub never_hit: Vec<&'static str>,
+        /// Marks that were hit at least once, but that no check macro
+        /// ([`crate::check!`], [`crate::check_count!`], [`crate::check_absent!`]
+        /// or their threaded forms) ever watched.
+        pub hit_but_never_checked: Vec<&'static str>,
+    }
+}
+
+/// Computes a coverage [`report::Report`] for every mark declared with
+/// [`def!`] anywhere in the binary, based on what ran before this call.
+/// A project can add a single test (or test-harness teardown) that calls
+/// this at the very end of the suite and asserts both lists are empty.
+///
+/// # Example
+///
+/// ```
+/// let report = cov_mark::report();
+/// assert!(report.never_hit.is_empty());
+/// assert!(report.hit_but_never_checked.is_empty());
+/// ```
+#[cfg(feature = "report")]
+pub fn report() -> report::Report {
+    let mut result = report::Report::default();
+    for report::MarkHandle(mark) in inventory::iter::<report::MarkHandle> {
+        if !mark.was_hit() {
+            // A mark that was checked but never hit either failed its guard's
+            // assertion already (for `check!`, or a `check_count!` that needs
+            // at least one hit) or was watched by a guard that allows zero
+            // hits (`check_absent!`, or a `check_count!` range including 0);
+            // either way it's not listed as never hit.
+            if !mark.was_checked() {
+                result.never_hit.push(mark.name());
+            }
+        } else if !mark.was_checked() {
+            result.hit_but_never_checked.push(mark.name());
+        }
+    }
+    result
+}
+
+#[doc(hidden)]
+#[cfg(any(feature = "enable", feature = "report", debug_assertions))]
 pub mod __rt {
+    pub use crate::Mark;
+    #[cfg(feature = "report")]
+    pub use inventory;
+
     use std::{
-        cell::{Cell, RefCell},
+        cell::RefCell,
+        ops::{Bound, RangeBounds},
         rc::Rc,
-        sync::atomic::{AtomicUsize, Ordering::Relaxed},
+        sync::{
+            atomic::{AtomicUsize, Ordering::Relaxed},
+            Arc, Mutex,
+        },
     };
 
     /// Even with
@@ -158,92 +455,227 @@ pub mod __rt {
     /// a `thread_local` generates significantly more verbose assembly on x86
     /// than atomic, so we'll use atomic for the fast path
     static LEVEL: AtomicUsize = AtomicUsize::new(0);
+    static GLOBAL_LEVEL: AtomicUsize = AtomicUsize::new(0);
 
     thread_local! {
         static ACTIVE: RefCell<Vec<Rc<GuardInner>>> = Default::default();
     }
 
+    static GLOBAL_ACTIVE: Mutex<Vec<Arc<GuardInner>>> = Mutex::new(Vec::new());
+
     #[inline(always)]
-    pub fn hit(key: &'static str) {
+    pub fn hit(mark: &'static Mark) {
+        #[cfg(feature = "report")]
+        mark.record_hit();
+
         if LEVEL.load(Relaxed) > 0 {
-            hit_cold(key);
+            hit_cold(mark);
+        }
+        if GLOBAL_LEVEL.load(Relaxed) > 0 {
+            hit_cold_global(mark);
+        }
+
+        #[cold]
+        fn hit_cold(mark: &'static Mark) {
+            ACTIVE.with(|it| it.borrow().iter().for_each(|g| g.hit(mark)))
         }
 
         #[cold]
-        fn hit_cold(key: &'static str) {
-            ACTIVE.with(|it| it.borrow().iter().for_each(|g| g.hit(key)))
+        fn hit_cold_global(mark: &'static Mark) {
+            GLOBAL_ACTIVE
+                .lock()
+                .unwrap()
+                .iter()
+                .for_each(|g| g.hit(mark))
+        }
+    }
+
+    /// What a [`Guard`] expects [`hit`] to have been called, checked when
+    /// the guard is dropped.
+    pub enum Expectation {
+        /// The mark must have been hit at least once ([`crate::check!`]).
+        AtLeastOnce,
+        /// The mark must have been hit a number of times within this bound
+        /// ([`crate::check_count!`], [`crate::check_absent!`]).
+        Range(Bound<usize>, Bound<usize>),
+    }
+
+    impl Expectation {
+        pub fn exact(count: usize) -> Expectation {
+            Expectation::Range(Bound::Included(count), Bound::Included(count))
+        }
+
+        pub fn range<R: RangeBounds<usize>>(range: R) -> Expectation {
+            Expectation::Range(cloned_bound(range.start_bound()), cloned_bound(range.end_bound()))
+        }
+    }
+
+    fn cloned_bound(bound: Bound<&usize>) -> Bound<usize> {
+        match bound {
+            Bound::Included(n) => Bound::Included(*n),
+            Bound::Excluded(n) => Bound::Excluded(*n),
+            Bound::Unbounded => Bound::Unbounded,
         }
     }
 
     struct GuardInner {
-        mark: &'static str,
-        hits: Cell<usize>,
-        expected_hits: Option<usize>,
+        mark: &'static Mark,
+        hits: AtomicUsize,
+        expected: Expectation,
+    }
+
+    enum Registration {
+        Local(Rc<GuardInner>),
+        Global(Arc<GuardInner>),
     }
 
     pub struct Guard {
-        inner: Rc<GuardInner>,
+        reg: Registration,
     }
 
     impl GuardInner {
-        fn hit(&self, key: &'static str) {
-            if key == self.mark {
-                self.hits.set(self.hits.get().saturating_add(1))
+        fn hit(&self, mark: &'static Mark) {
+            if std::ptr::eq(self.mark, mark) {
+                self.hits.fetch_add(1, Relaxed);
             }
         }
     }
 
     impl Guard {
-        pub fn new(mark: &'static str, expected_hits: Option<usize>) -> Guard {
-            let inner = GuardInner {
+        /// Only sees hits made on the same thread as this guard.
+        pub fn new(mark: &'static Mark, expected: Expectation) -> Guard {
+            #[cfg(feature = "report")]
+            mark.record_checked();
+
+            let inner = Rc::new(GuardInner {
                 mark,
-                hits: Cell::new(0),
-                expected_hits,
-            };
-            let inner = Rc::new(inner);
+                hits: AtomicUsize::new(0),
+                expected,
+            });
             LEVEL.fetch_add(1, Relaxed);
             ACTIVE.with(|it| it.borrow_mut().push(Rc::clone(&inner)));
-            Guard { inner }
+            Guard {
+                reg: Registration::Local(inner),
+            }
+        }
+
+        /// Sees hits made on any thread.
+        pub fn new_threaded(mark: &'static Mark, expected: Expectation) -> Guard {
+            #[cfg(feature = "report")]
+            mark.record_checked();
+
+            let inner = Arc::new(GuardInner {
+                mark,
+                hits: AtomicUsize::new(0),
+                expected,
+            });
+            GLOBAL_LEVEL.fetch_add(1, Relaxed);
+            GLOBAL_ACTIVE.lock().unwrap().push(Arc::clone(&inner));
+            Guard {
+                reg: Registration::Global(inner),
+            }
         }
     }
 
     impl Drop for Guard {
         fn drop(&mut self) {
-            LEVEL.fetch_sub(1, Relaxed);
-            let last = ACTIVE.with(|it| it.borrow_mut().pop());
-
-            if std::thread::panicking() {
-                return;
+            match &self.reg {
+                Registration::Local(inner) => {
+                    LEVEL.fetch_sub(1, Relaxed);
+                    let last = ACTIVE.with(|it| it.borrow_mut().pop());
+                    if std::thread::panicking() {
+                        return;
+                    }
+                    assert!(Rc::ptr_eq(&last.unwrap(), inner));
+                    check_hits(inner);
+                }
+                Registration::Global(inner) => {
+                    // Remove from the registry and drop GLOBAL_LEVEL under the
+                    // same lock that `hit_cold_global` scans under, so a hit
+                    // racing with this drop either observes the guard still
+                    // in the registry (and counts it) or observes it already
+                    // gone (because the removal, not just the level, has
+                    // happened) -- never a level that already reads
+                    // "inactive" while the guard is still registered.
+                    {
+                        let mut active = GLOBAL_ACTIVE.lock().unwrap();
+                        active.retain(|g| !Arc::ptr_eq(g, inner));
+                        GLOBAL_LEVEL.fetch_sub(1, Relaxed);
+                    }
+                    if std::thread::panicking() {
+                        return;
+                    }
+                    check_hits(inner);
+                }
             }
+        }
+    }
 
-            let last = last.unwrap();
-            assert!(Rc::ptr_eq(&last, &self.inner));
-            let hit_count = last.hits.get();
-            match last.expected_hits {
-                Some(hits) => assert!(
-                    hit_count == hits,
-                    "{} mark was hit {} times, expected {}",
-                    self.inner.mark,
-                    hit_count,
-                    hits
-                ),
-                None => assert!(hit_count > 0, "{} mark was not hit", self.inner.mark),
+    fn check_hits(inner: &GuardInner) {
+        let hit_count = inner.hits.load(Relaxed);
+        match &inner.expected {
+            Expectation::AtLeastOnce => {
+                assert!(hit_count > 0, "{} mark was not hit", inner.mark.name)
             }
+            Expectation::Range(start, end) => assert!(
+                (*start, *end).contains(&hit_count),
+                "{} mark was hit {} times, expected {}",
+                inner.mark.name,
+                hit_count,
+                describe_range(*start, *end),
+            ),
+        }
+    }
+
+    fn describe_range(start: Bound<usize>, end: Bound<usize>) -> String {
+        match (start, end) {
+            (Bound::Included(s), Bound::Included(e)) if s == e => format!("{s}"),
+            (Bound::Included(s), Bound::Included(e)) => format!("{s}..={e}"),
+            (Bound::Included(s), Bound::Excluded(e)) => format!("{s}..{e}"),
+            (Bound::Included(s), Bound::Unbounded) => format!("{s}.."),
+            (Bound::Unbounded, Bound::Included(e)) => format!("..={e}"),
+            (Bound::Unbounded, Bound::Excluded(e)) => format!("..{e}"),
+            (Bound::Unbounded, Bound::Unbounded) => "..".to_string(),
+            (start, end) => format!("{start:?}..{end:?}"),
         }
     }
 }
 
 #[doc(hidden)]
-#[cfg(not(feature = "enable"))]
+#[cfg(not(any(feature = "enable", feature = "report", debug_assertions)))]
 pub mod __rt {
+    pub use crate::Mark;
+    #[cfg(feature = "report")]
+    pub use inventory;
+
     #[inline(always)]
-    pub fn hit(_: &'static str) {}
+    pub fn hit(_: &'static Mark) {}
+
+    #[non_exhaustive]
+    pub struct Expectation;
+
+    impl Expectation {
+        #[allow(non_upper_case_globals)]
+        pub const AtLeastOnce: Expectation = Expectation;
+
+        pub fn exact(_: usize) -> Expectation {
+            Expectation
+        }
+
+        pub fn range<R: std::ops::RangeBounds<usize>>(_: R) -> Expectation {
+            Expectation
+        }
+    }
 
     #[non_exhaustive]
     pub struct Guard;
 
     impl Guard {
-        pub fn new(_: &'static str, _: Option<usize>) -> Guard {
+        pub fn new(_: &'static Mark, _: Expectation) -> Guard {
+            Guard
+        }
+
+        pub fn new_threaded(_: &'static Mark, _: Expectation) -> Guard {
             Guard
         }
     }
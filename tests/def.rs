@@ -1,3 +1,5 @@
+cov_mark::def!(save_divide_zero);
+
 fn safe_divide(dividend: u32, divisor: u32) -> u32 {
     if divisor == 0 {
         cov_mark::hit!(save_divide_zero);
@@ -6,6 +8,8 @@ fn safe_divide(dividend: u32, divisor: u32) -> u32 {
     dividend / divisor
 }
 
+cov_mark::def!(covered_dropper_drops);
+
 struct CoveredDropper;
 impl Drop for CoveredDropper {
     fn drop(&mut self) {
@@ -17,21 +21,28 @@ impl Drop for CoveredDropper {
 mod group {
     use super::*;
 
-    cov_mark::def!(save_divide_zero);
-
     #[test]
     fn test_safe_divide_by_zero() {
-        cov_mark::chk!(save_divide_zero);
+        cov_mark::check!(save_divide_zero);
         assert_eq!(safe_divide(92, 0), 0);
     }
 
-    cov_mark::def!(covered_dropper_drops);
-
     #[test]
-    #[cfg(feature = "thread-local")]
     fn test_drop_count() {
-        cov_mark::chk_cnt!(covered_dropper_drops, 2);
+        cov_mark::check_count!(covered_dropper_drops, 2);
         let _covered_dropper1 = CoveredDropper;
         let _covered_dropper2 = CoveredDropper;
     }
+
+    #[test]
+    fn test_drop_count_range() {
+        cov_mark::check_count!(covered_dropper_drops, 1..=2);
+        let _covered_dropper1 = CoveredDropper;
+    }
+
+    #[test]
+    fn test_safe_divide_never_zero() {
+        cov_mark::check_absent!(save_divide_zero);
+        assert_eq!(safe_divide(92, 4), 23);
+    }
 }
@@ -0,0 +1,26 @@
+#![cfg(feature = "report")]
+
+// Run with `--release --no-default-features --features report` too: that's
+// the "report" feature on its own, without `enable` or `debug_assertions`,
+// which is the scenario `report()` must still track hits correctly in.
+
+cov_mark::def!(report_hit_mark);
+cov_mark::def!(report_unhit_mark);
+cov_mark::def!(report_absent_mark);
+
+#[test]
+fn test_report() {
+    cov_mark::hit!(report_hit_mark);
+
+    let report = cov_mark::report();
+    assert!(report.never_hit.contains(&"report::report_unhit_mark"));
+    assert!(report.hit_but_never_checked.contains(&"report::report_hit_mark"));
+}
+
+#[test]
+fn test_report_excludes_checked_absent_marks() {
+    cov_mark::check_absent!(report_absent_mark);
+
+    let report = cov_mark::report();
+    assert!(!report.never_hit.contains(&"report::report_absent_mark"));
+}
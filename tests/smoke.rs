@@ -1,3 +1,5 @@
+cov_mark::def!(save_divide_zero);
+
 fn safe_divide(dividend: u32, divisor: u32) -> u32 {
     if divisor == 0 {
         cov_mark::hit!(save_divide_zero);
@@ -12,6 +14,8 @@ fn test_safe_divide_by_zero() {
     assert_eq!(safe_divide(92, 0), 0);
 }
 
+cov_mark::def!(covered_dropper_drops);
+
 struct CoveredDropper;
 impl Drop for CoveredDropper {
     fn drop(&mut self) {
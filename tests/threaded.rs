@@ -0,0 +1,44 @@
+cov_mark::def!(worker_ran);
+cov_mark::def!(worker_ran_twice);
+
+fn run_worker() {
+    cov_mark::hit!(worker_ran);
+}
+
+#[test]
+fn test_worker_runs() {
+    cov_mark::check_threaded!(worker_ran);
+    std::thread::spawn(run_worker).join().unwrap();
+}
+
+#[test]
+fn test_worker_runs_twice() {
+    cov_mark::check_count_threaded!(worker_ran_twice, 2);
+    let workers: Vec<_> = (0..2)
+        .map(|_| std::thread::spawn(|| cov_mark::hit!(worker_ran_twice)))
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
+
+cov_mark::def!(mark_a);
+cov_mark::def!(mark_b);
+
+#[test]
+fn test_multiple_marks_concurrently() {
+    cov_mark::check_threaded!(mark_a);
+    cov_mark::check_count_threaded!(mark_b, 3);
+
+    let workers: Vec<_> = (0..3)
+        .map(|_| {
+            std::thread::spawn(|| {
+                cov_mark::hit!(mark_a);
+                cov_mark::hit!(mark_b);
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}